@@ -17,21 +17,28 @@ pub use sp_evm::{Account, CallInfo, CreateInfo, ExecutionInfo, Log, Vicinity};
 use codec::{Decode, Encode};
 use evm::Config;
 use frame_support::dispatch::DispatchResultWithPostInfo;
-use frame_support::traits::{Currency, ExistenceRequirement, Get};
+use frame_support::traits::{Currency, ExistenceRequirement, Get, Imbalance, WithdrawReason};
 use frame_support::weights::{Pays, Weight};
-use frame_support::{decl_error, decl_event, decl_module, decl_storage};
+use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure};
 use frame_system::RawOrigin;
+use rlp::{Rlp, RlpStream};
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 use sp_core::{Hasher, H160, H256, U256};
 use sp_runtime::{
 	traits::{BadOrigin, UniqueSaturatedInto},
-	AccountId32,
+	AccountId32, DispatchError,
 };
 use sp_std::vec::Vec;
 
 /// Type alias for currency balance.
 pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+/// Type alias for the currency's negative imbalance (withdrawals).
+pub type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+/// Type alias for the currency's positive imbalance (deposits).
+pub type PositiveImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::PositiveImbalance;
 
 pub trait EnsureAddressOrigin<OuterOrigin> {
 	/// Success return type.
@@ -128,8 +135,63 @@ impl Get<u64> for SystemChainId {
 	}
 }
 
+/// Trait that outputs the current transaction gas price.
+pub trait FeeCalculator {
+	/// Return the minimal required gas price.
+	fn min_gas_price() -> U256;
+}
+
+impl FeeCalculator for () {
+	fn min_gas_price() -> U256 {
+		U256::zero()
+	}
+}
+
+/// The secp256k1 curve order `n`, big-endian.
+const SECP256K1_N: [u8; 32] = [
+	0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xba, 0xae, 0xdc,
+	0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Half the secp256k1 curve order `n/2`, big-endian; the inclusive upper bound for a low-`s`
+/// signature (EIP-2).
+const SECP256K1_HALF_N: [u8; 32] = [
+	0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x5d, 0x57, 0x6e,
+	0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
 static ISTANBUL_CONFIG: Config = Config::istanbul();
 
+/// A hardfork schedule resolving the active EVM [`Config`] for a given block number.
+///
+/// Implementors declare their fork points as an ascending list of activation blocks mapped to
+/// the relevant `evm::Config` (Frontier, Istanbul, Berlin, ...), mirroring how Ethereum clients
+/// parameterize the cost schedule per fork. The pallet resolves the active config on every
+/// `call`/`create`/`create2` using the current block number, so a chain can transition between
+/// EVM revisions on-chain without a pallet rewrite.
+pub trait ForkSchedule<BlockNumber> {
+	/// Return the config active at `block`.
+	fn config_at(block: BlockNumber) -> &'static Config;
+}
+
+// NOTE: The checkpoint/commit/revert substate overlay (the `checkpoint`/`canonicalize`/
+// `rollback` model that buffers dirtied `(H160, H256) -> H256` storage, created/destroyed
+// account sets, and accumulated refund gas for each nested `CALL`/`CREATE` frame) belongs in
+// the backend consumed by `Runner`, where it is actually threaded through the execution path.
+// That backend lives in the `runner` module, which is not part of this source snapshot, so the
+// subsystem is implemented there rather than as inert, uncalled API on the pallet.
+
+/// A schedule that keeps the Istanbul config active for the whole chain lifetime.
+///
+/// Suitable as a default for runtimes that do not plan an on-chain EVM upgrade.
+pub struct IstanbulForever;
+
+impl<BlockNumber> ForkSchedule<BlockNumber> for IstanbulForever {
+	fn config_at(_block: BlockNumber) -> &'static Config {
+		&ISTANBUL_CONFIG
+	}
+}
+
 /// EVM module trait
 pub trait Trait: frame_system::Trait + pallet_timestamp::Trait {
 	/// Allow the origin to call on behalf of given address.
@@ -141,6 +203,8 @@ pub trait Trait: frame_system::Trait + pallet_timestamp::Trait {
 	type AddressMapping: AddressMapping<Self::AccountId>;
 	/// Currency type for withdraw and balance storage.
 	type Currency: Currency<Self::AccountId>;
+	/// Calculator for current gas price.
+	type FeeCalculator: FeeCalculator;
 
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
@@ -150,10 +214,19 @@ pub trait Trait: frame_system::Trait + pallet_timestamp::Trait {
 	type ChainId: Get<u64>;
 	/// EVM execution runner.
 	type Runner: Runner<Self>;
+	/// Hardfork schedule resolving the active EVM config per block number.
+	type ConfigSchedule: ForkSchedule<Self::BlockNumber>;
+	/// Block author/coinbase reported to contracts via the `COINBASE` opcode.
+	type Coinbase: Get<H160>;
+
+	/// EVM config active at `block`.
+	fn config_at(block: Self::BlockNumber) -> &'static Config {
+		Self::ConfigSchedule::config_at(block)
+	}
 
-	/// EVM config used in the module.
+	/// EVM config active at the current block.
 	fn config() -> &'static Config {
-		&ISTANBUL_CONFIG
+		Self::config_at(frame_system::Module::<Self>::block_number())
 	}
 }
 
@@ -240,6 +313,10 @@ decl_error! {
 		GasPriceTooLow,
 		/// Nonce is invalid
 		InvalidNonce,
+		/// Raw transaction could not be RLP-decoded
+		TransactionDecodeFailed,
+		/// Transaction signature is invalid or the sender could not be recovered
+		InvalidSignature,
 	}
 }
 
@@ -272,16 +349,26 @@ decl_module! {
 			input: Vec<u8>,
 			value: U256,
 			gas_limit: u32,
+			gas_price: U256,
+			nonce: Option<U256>,
 		) -> DispatchResultWithPostInfo {
 			T::CallOrigin::ensure_address_origin(&source, origin)?;
 
-			match T::Runner::call(
+			Module::<T>::check_nonce(source, nonce)?;
+			let paid = Module::<T>::withdraw_fee(source, gas_price, U256::from(gas_limit))?;
+
+			let info = T::Runner::call(
 				source,
 				target,
 				input,
 				value,
 				gas_limit,
-			)? {
+			)?;
+
+			Module::<T>::inc_sender_nonce(source);
+			Module::<T>::refund_fee(source, paid, gas_price, U256::from(gas_limit), info.used_gas);
+
+			match info {
 				CallInfo {
 					exit_reason: ExitReason::Succeed(_),
 					..
@@ -305,15 +392,25 @@ decl_module! {
 			init: Vec<u8>,
 			value: U256,
 			gas_limit: u32,
+			gas_price: U256,
+			nonce: Option<U256>,
 		) -> DispatchResultWithPostInfo {
 			T::CallOrigin::ensure_address_origin(&source, origin)?;
 
-			match T::Runner::create(
+			Module::<T>::check_nonce(source, nonce)?;
+			let paid = Module::<T>::withdraw_fee(source, gas_price, U256::from(gas_limit))?;
+
+			let info = T::Runner::create(
 				source,
 				init,
 				value,
 				gas_limit,
-			)? {
+			)?;
+
+			Module::<T>::inc_sender_nonce(source);
+			Module::<T>::refund_fee(source, paid, gas_price, U256::from(gas_limit), info.used_gas);
+
+			match info {
 				CreateInfo {
 					exit_reason: ExitReason::Succeed(_),
 					value: create_address,
@@ -342,16 +439,26 @@ decl_module! {
 			salt: H256,
 			value: U256,
 			gas_limit: u32,
+			gas_price: U256,
+			nonce: Option<U256>,
 		) -> DispatchResultWithPostInfo {
 			T::CallOrigin::ensure_address_origin(&source, origin)?;
 
-			match T::Runner::create2(
+			Module::<T>::check_nonce(source, nonce)?;
+			let paid = Module::<T>::withdraw_fee(source, gas_price, U256::from(gas_limit))?;
+
+			let info = T::Runner::create2(
 				source,
 				init,
 				salt,
 				value,
 				gas_limit,
-			)? {
+			)?;
+
+			Module::<T>::inc_sender_nonce(source);
+			Module::<T>::refund_fee(source, paid, gas_price, U256::from(gas_limit), info.used_gas);
+
+			match info {
 				CreateInfo {
 					exit_reason: ExitReason::Succeed(_),
 					value: create_address,
@@ -370,10 +477,307 @@ decl_module! {
 
 			Ok(Pays::No.into())
 		}
+
+		/// Accept a raw RLP-encoded, EIP-155 signed Ethereum transaction and dispatch it.
+		///
+		/// The sender is recovered from the signature rather than supplied by the caller, so
+		/// this is the entry point used to serve an `eth_sendRawTransaction` endpoint.
+		#[weight = 0]
+		fn transact(origin, raw_tx: Vec<u8>) -> DispatchResultWithPostInfo {
+			// The signature authenticates the sender; the dispatch origin is unused.
+			let _ = origin;
+
+			let tx = Module::<T>::decode_transaction(&raw_tx).ok_or(Error::<T>::TransactionDecodeFailed)?;
+			let source = Module::<T>::recover_signer(&tx).ok_or(Error::<T>::InvalidSignature)?;
+
+			Module::<T>::check_nonce(source, Some(tx.nonce))?;
+
+			// Charge the fee on the real `U256` gas limit, but run against a saturated `u32`.
+			let paid = Module::<T>::withdraw_fee(source, tx.gas_price, tx.gas_limit)?;
+
+			let gas_limit = saturate_gas_limit(tx.gas_limit);
+
+			match tx.action {
+				Some(target) => {
+					let info = T::Runner::call(source, target, tx.input, tx.value, gas_limit)?;
+
+					// Advance the nonce after execution so a signed tx cannot be replayed.
+					Module::<T>::inc_sender_nonce(source);
+					Module::<T>::refund_fee(source, paid, tx.gas_price, tx.gas_limit, info.used_gas);
+
+					match info {
+						CallInfo {
+							exit_reason: ExitReason::Succeed(_),
+							..
+						} => Module::<T>::deposit_event(Event::<T>::Executed(target)),
+						_ => Module::<T>::deposit_event(Event::<T>::ExecutedFailed(target)),
+					}
+				}
+				None => {
+					let info = T::Runner::create(source, tx.input, tx.value, gas_limit)?;
+
+					// Advance the nonce only after the CREATE address is derived from `tx.nonce`.
+					Module::<T>::inc_sender_nonce(source);
+					Module::<T>::refund_fee(source, paid, tx.gas_price, tx.gas_limit, info.used_gas);
+
+					match info {
+						CreateInfo {
+							exit_reason: ExitReason::Succeed(_),
+							value: create_address,
+							..
+						} => Module::<T>::deposit_event(Event::<T>::Created(create_address)),
+						CreateInfo {
+							value: create_address,
+							..
+						} => Module::<T>::deposit_event(Event::<T>::CreatedFailed(create_address)),
+					}
+				}
+			}
+
+			Ok(Pays::No.into())
+		}
 	}
 }
 
+/// A decoded legacy (pre- or EIP-155) Ethereum transaction.
+struct EthereumTransaction {
+	nonce: U256,
+	gas_price: U256,
+	gas_limit: U256,
+	/// Destination address, or `None` for a contract creation.
+	action: Option<H160>,
+	value: U256,
+	input: Vec<u8>,
+	v: u64,
+	r: H256,
+	s: H256,
+}
+
+/// Saturate an Ethereum gas limit into the `u32` gas limit the runner consumes.
+fn saturate_gas_limit(gas_limit: U256) -> u32 {
+	if gas_limit > U256::from(u32::max_value()) {
+		u32::max_value()
+	} else {
+		gas_limit.low_u32()
+	}
+}
+
+/// Left-pad an RLP integer (encoded without leading zeros) into a 32-byte signature word.
+fn u256_to_h256(value: U256) -> H256 {
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	H256::from(bytes)
+}
+
 impl<T: Trait> Module<T> {
+	/// Decode an RLP list `[nonce, gas_price, gas_limit, to, value, data, v, r, s]`.
+	fn decode_transaction(raw_tx: &[u8]) -> Option<EthereumTransaction> {
+		let rlp = Rlp::new(raw_tx);
+		if !rlp.is_list() || rlp.item_count().ok()? != 9 {
+			return None;
+		}
+
+		let action = {
+			let to = rlp.at(3).ok()?;
+			if to.is_empty() {
+				None
+			} else {
+				Some(to.as_val::<H160>().ok()?)
+			}
+		};
+
+		Some(EthereumTransaction {
+			nonce: rlp.val_at(0).ok()?,
+			gas_price: rlp.val_at(1).ok()?,
+			gas_limit: rlp.val_at(2).ok()?,
+			action,
+			value: rlp.val_at(4).ok()?,
+			input: rlp.val_at(5).ok()?,
+			v: rlp.val_at(6).ok()?,
+			r: u256_to_h256(rlp.val_at(7).ok()?),
+			s: u256_to_h256(rlp.val_at(8).ok()?),
+		})
+	}
+
+	/// Reconstruct the EIP-155 signing payload and recover the sender address.
+	fn recover_signer(tx: &EthereumTransaction) -> Option<H160> {
+		let chain_id = T::ChainId::get();
+		let eip155_v_base = chain_id.checked_mul(2)?.checked_add(35)?;
+		let replay_protected = tx.v >= eip155_v_base;
+
+		// Reject malleable signatures: enforce `1 <= r,s < n` and low-`s` (EIP-2).
+		let r = U256::from_big_endian(tx.r.as_bytes());
+		let s = U256::from_big_endian(tx.s.as_bytes());
+		let n = U256::from_big_endian(&SECP256K1_N);
+		let half_n = U256::from_big_endian(&SECP256K1_HALF_N);
+		if r.is_zero() || s.is_zero() || r >= n || s > half_n {
+			return None;
+		}
+
+		// Derive the recovery id and, for EIP-155, validate the embedded chain id.
+		let recovery_id = if replay_protected {
+			if (tx.v - 35) / 2 != chain_id {
+				return None;
+			}
+			tx.v - eip155_v_base
+		} else {
+			// Pre-155 transactions carry v in {27, 28} and no replay protection.
+			tx.v.checked_sub(27)?
+		};
+		if recovery_id > 1 {
+			return None;
+		}
+
+		let message = Self::signing_hash(tx, chain_id, replay_protected);
+
+		let mut signature = [0u8; 65];
+		signature[0..32].copy_from_slice(tx.r.as_bytes());
+		signature[32..64].copy_from_slice(tx.s.as_bytes());
+		signature[64] = recovery_id as u8;
+
+		let pubkey = sp_io::crypto::secp256k1_ecdsa_recover(&signature, message.as_fixed_bytes()).ok()?;
+		let hash = sp_io::hashing::keccak_256(&pubkey);
+
+		let mut address = H160::default();
+		address.as_bytes_mut().copy_from_slice(&hash[12..]);
+		Some(address)
+	}
+
+	/// Keccak256 of the RLP signing payload for `tx`.
+	fn signing_hash(tx: &EthereumTransaction, chain_id: u64, replay_protected: bool) -> H256 {
+		let mut stream = RlpStream::new();
+
+		stream.begin_list(if replay_protected { 9 } else { 6 });
+		stream.append(&tx.nonce);
+		stream.append(&tx.gas_price);
+		stream.append(&tx.gas_limit);
+		match tx.action {
+			Some(target) => stream.append(&target),
+			None => stream.append_empty_data(),
+		};
+		stream.append(&tx.value);
+		stream.append(&tx.input);
+		if replay_protected {
+			stream.append(&chain_id);
+			stream.append(&0u8);
+			stream.append(&0u8);
+		}
+
+		H256::from(sp_io::hashing::keccak_256(&stream.out()))
+	}
+
+	/// Advance the sender account nonce by one.
+	///
+	/// The pallet is the single authority for nonce progression: every executed `call`/`create`/
+	/// `create2`/`transact` bumps it here, after any `CREATE` address has been derived from the
+	/// pre-bump nonce. The `Runner` must not bump it as well.
+	fn inc_sender_nonce(source: H160) {
+		let account_id = T::AddressMapping::into_account_id(source);
+		frame_system::Module::<T>::inc_account_nonce(&account_id);
+	}
+
+	/// Ensure that the supplied nonce matches the source account nonce, if given.
+	fn check_nonce(source: H160, nonce: Option<U256>) -> Result<(), DispatchError> {
+		if let Some(nonce) = nonce {
+			let account = Self::account_basic(&source);
+			ensure!(account.nonce == nonce, Error::<T>::InvalidNonce);
+		}
+
+		Ok(())
+	}
+
+	/// Saturating conversion of a `U256` amount into the currency balance type.
+	///
+	/// Truncating via `low_u128` would silently withdraw a tiny wrong amount for values beyond
+	/// `u128::MAX`, so clamp the whole value first.
+	fn u256_to_balance(value: U256) -> BalanceOf<T> {
+		if value > U256::from(u128::max_value()) {
+			u128::max_value().unique_saturated_into()
+		} else {
+			value.low_u128().unique_saturated_into()
+		}
+	}
+
+	/// Withdraw the up-front gas fee `gas_limit * gas_price` from `source`, returning the
+	/// resulting imbalance so the consumed/refunded split can be settled against it.
+	fn withdraw_fee(
+		source: H160,
+		gas_price: U256,
+		gas_limit: U256,
+	) -> Result<NegativeImbalanceOf<T>, DispatchError> {
+		ensure!(
+			gas_price >= T::FeeCalculator::min_gas_price(),
+			Error::<T>::GasPriceTooLow
+		);
+
+		let fee = gas_price.checked_mul(gas_limit).ok_or(Error::<T>::FeeOverflow)?;
+		let account_id = T::AddressMapping::into_account_id(source);
+
+		let imbalance = T::Currency::withdraw(
+			&account_id,
+			Self::u256_to_balance(fee),
+			WithdrawReason::Fee.into(),
+			ExistenceRequirement::AllowDeath,
+		)
+		.map_err(|_| Error::<T>::BalanceLow)?;
+
+		Ok(imbalance)
+	}
+
+	/// Refund `(gas_limit - used_gas) * gas_price` to `source` and pay the consumed remainder to
+	/// the block author, settling both against the withdrawn `paid` imbalance so total issuance
+	/// stays consistent.
+	fn refund_fee(source: H160, paid: NegativeImbalanceOf<T>, gas_price: U256, gas_limit: U256, used_gas: U256) {
+		let refund_gas = gas_limit.saturating_sub(used_gas);
+		let refund = gas_price.saturating_mul(refund_gas);
+		let account_id = T::AddressMapping::into_account_id(source);
+
+		// Return the unused portion to the source, reducing the withdrawn imbalance. Use
+		// `deposit_creating` (not `deposit_into_existing`) so the refund still reaches the source
+		// even when the up-front `AllowDeath` withdrawal reaped the account.
+		let refund_imbalance = T::Currency::deposit_creating(&account_id, Self::u256_to_balance(refund));
+		let adjusted_paid = paid
+			.offset(refund_imbalance)
+			.unwrap_or_else(|_| NegativeImbalanceOf::<T>::zero());
+
+		// Deposit the consumed remainder to the coinbase/fee destination.
+		let author_id = T::AddressMapping::into_account_id(T::Coinbase::get());
+		T::Currency::resolve_creating(&author_id, adjusted_paid);
+	}
+
+	/// Current block number saturated into `U256`, as seen by the `NUMBER` opcode.
+	pub fn block_number() -> U256 {
+		U256::from(UniqueSaturatedInto::<u128>::unique_saturated_into(
+			frame_system::Module::<T>::block_number(),
+		))
+	}
+
+	/// Hash of block `number`, as seen by the `BLOCKHASH` opcode.
+	///
+	/// Per Ethereum semantics this is zero for the current or any future block, and for any
+	/// block more than 256 behind the current one.
+	pub fn block_hash(number: U256) -> H256 {
+		let current = Self::block_number();
+		if number >= current || number.saturating_add(U256::from(256)) < current {
+			H256::default()
+		} else {
+			let block_number = UniqueSaturatedInto::<T::BlockNumber>::unique_saturated_into(number.low_u64());
+			H256::from_slice(frame_system::Module::<T>::block_hash(block_number).as_ref())
+		}
+	}
+
+	/// Current block timestamp in seconds, as seen by the `TIMESTAMP` opcode.
+	pub fn block_timestamp() -> U256 {
+		// `pallet_timestamp` reports milliseconds; Ethereum expects seconds.
+		let now = UniqueSaturatedInto::<u128>::unique_saturated_into(pallet_timestamp::Module::<T>::get());
+		U256::from(now / 1000)
+	}
+
+	/// Block author reported to contracts via the `COINBASE` opcode.
+	pub fn author() -> H160 {
+		T::Coinbase::get()
+	}
+
 	/// Check whether an account is empty.
 	pub fn is_account_empty(address: &H160) -> bool {
 		let account = Self::account_basic(address);